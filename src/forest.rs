@@ -0,0 +1,190 @@
+use std::collections::BinaryHeap;
+
+use crate::kdtree::{KDTree, PairDistanceValue};
+
+struct Bucket<T, D, FMap, FDist, FRadius>
+where
+    T: Clone + PartialEq,
+    D: PartialOrd,
+    FMap: Fn(&T) -> D + Clone,
+    FDist: Fn(&T, &T) -> D + Clone,
+    FRadius: Fn(&D, &D) -> D + Clone,
+{
+    points: Vec<T>,
+    tombstones: Vec<T>,
+    tree: KDTree<T, D, FMap, FDist, FRadius>,
+}
+
+impl<T, D, FMap, FDist, FRadius> Bucket<T, D, FMap, FDist, FRadius>
+where
+    T: Clone + PartialEq,
+    D: PartialOrd,
+    FMap: Fn(&T) -> D + Clone,
+    FDist: Fn(&T, &T) -> D + Clone,
+    FRadius: Fn(&D, &D) -> D + Clone,
+{
+    fn build(points: Vec<T>, dimensions: Vec<FMap>, dist_func: FDist, radius_func: FRadius) -> Self {
+        let tree = KDTree::new(points.clone(), dimensions, dist_func, radius_func);
+        Self {
+            points,
+            tombstones: Vec::new(),
+            tree,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    fn is_tombstoned(&self, value: &T) -> bool {
+        self.tombstones.contains(value)
+    }
+}
+
+/// A logarithmic static-to-dynamic kd-forest: a vector of immutable `KDTree`s
+/// where slot `i` holds at most 2^i points (exactly 2^i until points are
+/// removed), giving amortized O(log n) inserts over a structure that is
+/// otherwise frozen after construction.
+pub struct KDForest<T, D, FMap, FDist, FRadius>
+where
+    T: Clone + PartialEq,
+    D: PartialOrd + Clone,
+    FMap: Fn(&T) -> D + Clone,
+    FDist: Fn(&T, &T) -> D + Clone,
+    FRadius: Fn(&D, &D) -> D + Clone,
+{
+    buckets: Vec<Option<Bucket<T, D, FMap, FDist, FRadius>>>,
+    dimensions: Vec<FMap>,
+    dist_func: FDist,
+    radius_func: FRadius,
+}
+
+impl<T, D, FMap, FDist, FRadius> KDForest<T, D, FMap, FDist, FRadius>
+where
+    T: Clone + PartialEq,
+    D: PartialOrd + Clone,
+    FMap: Fn(&T) -> D + Clone,
+    FDist: Fn(&T, &T) -> D + Clone,
+    FRadius: Fn(&D, &D) -> D + Clone,
+{
+    pub fn new(dimensions: Vec<FMap>, dist_func: FDist, radius_func: FRadius) -> Self {
+        Self {
+            buckets: Vec::new(),
+            dimensions,
+            dist_func,
+            radius_func,
+        }
+    }
+
+    /// Inserts a point, merging the trees of the occupied slots `0, 1, ...`
+    /// (dropping their tombstoned points) into the first empty slot, so that
+    /// each slot holds at most one tree again.
+    pub fn insert(&mut self, point: T) {
+        let mut merged = vec![point];
+        let mut i = 0;
+
+        while i < self.buckets.len() && self.buckets[i].is_some() {
+            let bucket = self.buckets[i].take().unwrap();
+            let tombstones = bucket.tombstones;
+            let live_points = bucket
+                .points
+                .into_iter()
+                .filter(|p| !tombstones.contains(p));
+            merged.extend(live_points);
+            i += 1;
+        }
+
+        while self.buckets.len() <= i {
+            self.buckets.push(None);
+        }
+
+        self.buckets[i] = Some(Bucket::build(
+            merged,
+            self.dimensions.clone(),
+            self.dist_func.clone(),
+            self.radius_func.clone(),
+        ));
+    }
+
+    /// Soft-deletes `point`: it is tombstoned and skipped by future queries.
+    /// Once a bucket's tombstones exceed half its size, that bucket is
+    /// rebuilt from its live points. Returns `true` if `point` was found.
+    pub fn remove(&mut self, point: &T) -> bool {
+        for slot in self.buckets.iter_mut() {
+            let Some(bucket) = slot else { continue };
+
+            if bucket.is_tombstoned(point) || !bucket.points.contains(point) {
+                continue;
+            }
+
+            bucket.tombstones.push(point.clone());
+
+            if bucket.tombstones.len() * 2 > bucket.len() {
+                let live_points: Vec<T> = bucket
+                    .points
+                    .iter()
+                    .filter(|p| !bucket.tombstones.contains(p))
+                    .cloned()
+                    .collect();
+
+                *bucket = Bucket::build(
+                    live_points,
+                    self.dimensions.clone(),
+                    self.dist_func.clone(),
+                    self.radius_func.clone(),
+                );
+            }
+
+            return true;
+        }
+
+        false
+    }
+
+    pub fn find_k_nearest_neighbors(&self, target: &T, k: usize) -> Vec<T> {
+        let mut heap: BinaryHeap<PairDistanceValue<T, D>> = BinaryHeap::new();
+
+        for bucket in self.buckets.iter().flatten() {
+            // Tombstoned points get filtered out below, so over-fetch enough
+            // of the bucket's own top-k to guarantee k live candidates remain.
+            let fetch_k = k + bucket.tombstones.len();
+
+            for value in bucket.tree.find_k_nearest_neighbors(target, fetch_k) {
+                if bucket.is_tombstoned(&value) {
+                    continue;
+                }
+
+                let dist = (self.dist_func)(target, &value);
+
+                if heap.len() < k {
+                    heap.push(PairDistanceValue { value, dist });
+                } else if heap.peek().is_some_and(|max| dist < max.dist) {
+                    heap.pop();
+                    heap.push(PairDistanceValue { value, dist });
+                }
+            }
+        }
+
+        let mut pairs = heap.into_vec();
+        pairs.sort_by(|a, b| {
+            a.dist
+                .partial_cmp(&b.dist)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        pairs.into_iter().map(|p| p.value).collect()
+    }
+
+    pub fn find_within_radius(&self, target: &T, radius: D) -> Vec<T> {
+        let mut result = Vec::new();
+
+        for bucket in self.buckets.iter().flatten() {
+            for value in bucket.tree.find_within_radius(target, radius.clone()) {
+                if !bucket.is_tombstoned(&value) {
+                    result.push(value);
+                }
+            }
+        }
+
+        result
+    }
+}
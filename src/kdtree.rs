@@ -3,16 +3,27 @@ use std::{collections::BinaryHeap, fmt::{Debug, Display}};
 #[derive(Debug)]
 struct Node<T> {
     value: T,
+    split_dim: usize,
     left: Option<Box<Node<T>>>,
     right: Option<Box<Node<T>>>,
 }
 
-struct PairDistanceValue<T, D>
+/// Controls how `build_node` picks the splitting axis at each level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitStrategy {
+    /// Cycle through the dimensions in order, the classic kd-tree layout.
+    RoundRobin,
+    /// At each node, split on whichever dimension has the widest spread
+    /// over the current slice. Better balanced subtrees on anisotropic data.
+    WidestSpread,
+}
+
+pub(crate) struct PairDistanceValue<T, D>
 where
     D: PartialOrd + PartialEq,
 {
-    value: T,
-    dist: D,
+    pub(crate) value: T,
+    pub(crate) dist: D,
 }
 
 impl<T, D> PartialEq for PairDistanceValue<T, D>
@@ -46,6 +57,18 @@ where
     }
 }
 
+/// A kd-tree over `T`, ordered by `D`.
+///
+/// `dist_func` and `radius_func` are an order embedding: they only need to be
+/// monotonic with the true distance, not equal to it. Concretely, for any
+/// `a, b, c`, `dist_func(a, b) <= dist_func(a, c)` must hold iff the true
+/// distance between `a` and `b` is at most the true distance between `a` and
+/// `c`, and `radius_func` must produce values in that same comparison space
+/// so the splitting-plane prune in `knn` stays correct. This lets callers use
+/// a cheap comparison value (e.g. squared Euclidean distance, skipping the
+/// `sqrt`) for every heap ordering and pruning decision, and recover the real
+/// distance only for the handful of results actually returned, via
+/// `with_materializer`.
 pub struct KDTree<T, D, FMap, FDist, FRadius>
 where
     T: Clone,
@@ -58,6 +81,7 @@ where
     dimensions: Vec<FMap>,
     dist_func: FDist,
     radius_func: FRadius,
+    materialize_dist: Option<Box<dyn Fn(&D) -> D>>,
 }
 
 impl<T, D, FMap, FDist, FRadius> KDTree<T, D, FMap, FDist, FRadius>
@@ -69,19 +93,49 @@ where
     FRadius: Fn(&D, &D) -> D,
 {
     pub fn new(
+        data: Vec<T>,
+        dimensions: Vec<FMap>,
+        dist_func: FDist,
+        radius_func: FRadius,
+    ) -> Self {
+        Self::new_with_strategy(
+            data,
+            dimensions,
+            dist_func,
+            radius_func,
+            SplitStrategy::RoundRobin,
+        )
+    }
+
+    pub fn new_with_strategy(
         mut data: Vec<T>,
         dimensions: Vec<FMap>,
         dist_func: FDist,
         radius_func: FRadius,
+        strategy: SplitStrategy,
     ) -> Self {
+        let root = Self::build_node(&mut data, &dimensions, 0, strategy, &radius_func);
         Self {
-            root: Self::build_node(&mut data, &dimensions, 0),
+            root,
             dimensions,
             dist_func,
             radius_func,
+            materialize_dist: None,
         }
     }
 
+    /// Attaches a closure that converts the cheap comparison value produced
+    /// by `dist_func` into the real distance, applied only when results are
+    /// read back through `find_k_nearest_neighbors_with_dist`. See the
+    /// type-level doc comment for the monotonicity invariant this relies on.
+    pub fn with_materializer<F>(mut self, materialize_dist: F) -> Self
+    where
+        F: Fn(&D) -> D + 'static,
+    {
+        self.materialize_dist = Some(Box::new(materialize_dist));
+        self
+    }
+
     fn partition(data: &mut [T], dimension: &FMap) -> usize {
         let len = data.len();
         let pivot = dimension(&data[len - 1]);
@@ -119,30 +173,102 @@ where
     //     });
     // }
 
+    fn widest_spread_dimension(data: &[T], dimensions: &Vec<FMap>, radius_func: &FRadius) -> usize {
+        let mut best_dim = 0;
+        let mut best_spread: Option<D> = None;
+
+        for (dim_ind, dimension) in dimensions.iter().enumerate() {
+            let mut min = dimension(&data[0]);
+            let mut max = dimension(&data[0]);
+            for item in data.iter().skip(1) {
+                let coord = dimension(item);
+                if coord < min {
+                    min = coord;
+                } else if coord > max {
+                    max = coord;
+                }
+            }
+
+            let spread = radius_func(&max, &min);
+            let is_wider = match &best_spread {
+                None => true,
+                Some(current) => spread > *current,
+            };
+
+            if is_wider {
+                best_spread = Some(spread);
+                best_dim = dim_ind;
+            }
+        }
+
+        best_dim
+    }
+
     fn build_node(
         data: &mut [T],
         dimensions: &Vec<FMap>,
         dimension_ind: usize,
+        strategy: SplitStrategy,
+        radius_func: &FRadius,
     ) -> Option<Box<Node<T>>> {
         if data.is_empty() {
             return None;
         }
 
+        let split_dim = match strategy {
+            SplitStrategy::RoundRobin => dimension_ind,
+            SplitStrategy::WidestSpread => Self::widest_spread_dimension(data, dimensions, radius_func),
+        };
+
         let median_ind = data.len() / 2;
-        let median = Self::quick_selection(data, &dimensions[dimension_ind], median_ind).clone();
+        let median = Self::quick_selection(data, &dimensions[split_dim], median_ind).clone();
 
         let next_dimension_ind = (dimension_ind + 1) % dimensions.len();
 
         Some(Box::new(Node {
             value: median,
-            left: Self::build_node(&mut data[..median_ind], dimensions, next_dimension_ind),
-            right: Self::build_node(&mut data[median_ind + 1..], dimensions, next_dimension_ind),
+            split_dim,
+            left: Self::build_node(&mut data[..median_ind], dimensions, next_dimension_ind, strategy, radius_func),
+            right: Self::build_node(&mut data[median_ind + 1..], dimensions, next_dimension_ind, strategy, radius_func),
         }))
     }
 
+    pub fn find_within_radius(&self, target: &T, radius: D) -> Vec<T> {
+        let mut result = Vec::new();
+        self.radius_search(target, &radius, &self.root, &mut result);
+        result
+    }
+
+    fn radius_search(&self, target: &T, radius: &D, node: &Option<Box<Node<T>>>, result: &mut Vec<T>) {
+        match node {
+            None => return,
+            Some(node) => {
+                let value = &node.value;
+                let dist = (self.dist_func)(target, value);
+
+                if dist <= *radius {
+                    result.push(value.clone());
+                }
+
+                let dimension = &self.dimensions[node.split_dim];
+                let (near, far) = if dimension(target) < dimension(value) {
+                    (&node.left, &node.right)
+                } else {
+                    (&node.right, &node.left)
+                };
+
+                self.radius_search(target, radius, near, result);
+
+                if (self.radius_func)(&dimension(target), &dimension(value)) <= *radius {
+                    self.radius_search(target, radius, far, result);
+                }
+            }
+        }
+    }
+
     pub fn find_k_nearest_neighbors(&self, target: &T, k: usize) -> Vec<T> {
         let mut heap = BinaryHeap::new();
-        self.knn(target, k, &self.root, 0, &mut heap);
+        self.knn(target, k, &self.root, &mut heap);
         let mut pairs = heap.into_vec();
         pairs.sort_by(|a, b| {
             a.dist
@@ -152,12 +278,35 @@ where
         pairs.iter().map(|p| p.value.clone()).collect()
     }
 
+    /// Like `find_k_nearest_neighbors`, but also returns the distance to
+    /// each neighbor, run through `with_materializer`'s closure if one was
+    /// set (otherwise the raw comparison value from `dist_func` is used).
+    pub fn find_k_nearest_neighbors_with_dist(&self, target: &T, k: usize) -> Vec<(T, D)> {
+        let mut heap = BinaryHeap::new();
+        self.knn(target, k, &self.root, &mut heap);
+        let mut pairs = heap.into_vec();
+        pairs.sort_by(|a, b| {
+            a.dist
+                .partial_cmp(&b.dist)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        pairs
+            .into_iter()
+            .map(|p| {
+                let dist = match &self.materialize_dist {
+                    Some(materialize) => materialize(&p.dist),
+                    None => p.dist,
+                };
+                (p.value, dist)
+            })
+            .collect()
+    }
+
     fn knn(
         &self,
         target: &T,
         k: usize,
         node: &Option<Box<Node<T>>>,
-        dimension_ind: usize,
         heap: &mut BinaryHeap<PairDistanceValue<T, D>>,
     ) {
         match node {
@@ -179,22 +328,94 @@ where
                     });
                 }
 
-                let dimension = &self.dimensions[dimension_ind];
+                let dimension = &self.dimensions[node.split_dim];
                 let (near, far) = if dimension(target) < dimension(value) {
                     (&node.left, &node.right)
                 } else {
                     (&node.right, &node.left)
                 };
 
-                let new_dimension_ind = (dimension_ind + 1) % self.dimensions.len();
-                self.knn(target, k, near, new_dimension_ind, heap);
+                self.knn(target, k, near, heap);
 
                 if heap.len() < k
                     || heap.peek().is_some_and(|max| {
                         (self.radius_func)(&dimension(target), &dimension(value)) < max.dist
                     })
                 {
-                    self.knn(target, k, far, new_dimension_ind, heap);
+                    self.knn(target, k, far, heap);
+                }
+            }
+        }
+    }
+}
+
+impl<T, D, FMap, FDist, FRadius> KDTree<T, D, FMap, FDist, FRadius>
+where
+    T: Clone,
+    D: PartialOrd + Copy + std::ops::Add<Output = D> + std::ops::Mul<Output = D>,
+    FMap: Fn(&T) -> D,
+    FDist: Fn(&T, &T) -> D,
+    FRadius: Fn(&D, &D) -> D,
+{
+    /// Like `find_k_nearest_neighbors`, but only descends into a far subtree
+    /// when it could beat the current k-th distance by more than a `(1 +
+    /// epsilon)` margin. Returned neighbors are within a `(1 + epsilon)`
+    /// factor of the true k nearest, in exchange for visiting far fewer nodes.
+    pub fn find_k_nearest_neighbors_approx(&self, target: &T, k: usize, epsilon: D) -> Vec<T> {
+        let mut heap = BinaryHeap::new();
+        self.knn_approx(target, k, epsilon, &self.root, &mut heap);
+        let mut pairs = heap.into_vec();
+        pairs.sort_by(|a, b| {
+            a.dist
+                .partial_cmp(&b.dist)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        pairs.iter().map(|p| p.value.clone()).collect()
+    }
+
+    fn knn_approx(
+        &self,
+        target: &T,
+        k: usize,
+        epsilon: D,
+        node: &Option<Box<Node<T>>>,
+        heap: &mut BinaryHeap<PairDistanceValue<T, D>>,
+    ) {
+        match node {
+            None => return,
+            Some(node) => {
+                let value = &node.value;
+                let dist = (self.dist_func)(target, value);
+
+                if heap.len() < k {
+                    heap.push(PairDistanceValue {
+                        value: value.clone(),
+                        dist,
+                    });
+                } else if heap.peek().is_some_and(|max| dist < max.dist) {
+                    heap.pop();
+                    heap.push(PairDistanceValue {
+                        value: value.clone(),
+                        dist,
+                    });
+                }
+
+                let dimension = &self.dimensions[node.split_dim];
+                let (near, far) = if dimension(target) < dimension(value) {
+                    (&node.left, &node.right)
+                } else {
+                    (&node.right, &node.left)
+                };
+
+                self.knn_approx(target, k, epsilon, near, heap);
+
+                if heap.len() < k
+                    || heap.peek().is_some_and(|max| {
+                        let far_dist = (self.radius_func)(&dimension(target), &dimension(value));
+                        far_dist + far_dist * epsilon < max.dist
+                    })
+                {
+                    self.knn_approx(target, k, epsilon, far, heap);
                 }
             }
         }
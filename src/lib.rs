@@ -1,6 +1,8 @@
+mod forest;
 mod kdtree;
+mod vptree;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 struct Point {
     x: f64,
     y: f64,
@@ -8,11 +10,11 @@ struct Point {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Point, kdtree};
+    use crate::{Point, forest, kdtree, vptree};
 
     #[test]
     fn test_kdtree_new() {
-        let mut data = [
+        let data = vec![
             Point { x: 615.0, y: 40.0 },
             Point { x: 207.0, y: 313.0 },
             Point { x: 751.0, y: 177.0 },
@@ -30,14 +32,14 @@ mod tests {
 
         let radius_func = |d1: &f64, d2: &f64| (d1 - d2).abs();
 
-        let new_kdtree = kdtree::KDTree::new(&mut data, dimensions, dist_func, radius_func);
+        let new_kdtree = kdtree::KDTree::new(data, dimensions, dist_func, radius_func);
 
         println!("{}", new_kdtree)
     }
 
     #[test]
     fn test_kdtree_find_k_nearest_neighbors_1() {
-        let mut data = [
+        let data = vec![
             Point { x: 272.0, y: 59.0 },
             Point { x: 481.0, y: 144.0 },
             Point { x: 915.0, y: 157.0 },
@@ -68,16 +70,16 @@ mod tests {
 
         let radius_func = |d1: &f64, d2: &f64| (d1 - d2).abs();
 
-        let tree = kdtree::KDTree::new(&mut data, dimensions, dist_func, radius_func);
+        let tree = kdtree::KDTree::new(data, dimensions, dist_func, radius_func);
 
-        let neighbors = tree.find_k_nearest_neighbors(Point { x: 782.0, y: 780.0 }, 1);
+        let neighbors = tree.find_k_nearest_neighbors(&Point { x: 782.0, y: 780.0 }, 1);
 
         println!("{:?}", neighbors)
     }
 
     #[test]
     fn test_kdtree_find_k_nearest_neighbors_2() {
-        let mut data = [
+        let data = vec![
             Point { x: 272.0, y: 59.0 },
             Point { x: 481.0, y: 144.0 },
             Point { x: 915.0, y: 157.0 },
@@ -108,9 +110,202 @@ mod tests {
 
         let radius_func = |d1: &f64, d2: &f64| (d1 - d2).abs();
 
-        let tree = kdtree::KDTree::new(&mut data, dimensions, dist_func, radius_func);
+        let tree = kdtree::KDTree::new(data, dimensions, dist_func, radius_func);
 
-        let neighbors = tree.find_k_nearest_neighbors(Point { x: 260.0, y: 585.0 }, 5);
+        let neighbors = tree.find_k_nearest_neighbors(&Point { x: 260.0, y: 585.0 }, 5);
+
+        println!("{:?}", neighbors)
+    }
+
+    #[test]
+    fn test_kdtree_find_within_radius() {
+        let data = vec![
+            Point { x: 272.0, y: 59.0 },
+            Point { x: 481.0, y: 144.0 },
+            Point { x: 915.0, y: 157.0 },
+            Point { x: 259.0, y: 189.0 },
+            Point { x: 913.0, y: 276.0 },
+            Point { x: 139.0, y: 310.0 },
+            Point { x: 821.0, y: 386.0 },
+            Point { x: 622.0, y: 410.0 },
+            Point { x: 281.0, y: 467.0 },
+            Point { x: 43.0, y: 480.0 },
+        ];
+
+        let dimensions = vec![|p: &Point| p.x, |p: &Point| p.y];
+
+        let dist_func = |p1: &Point, p2: &Point| {
+            ((p1.x - p2.x) * (p1.x - p2.x) + (p1.y - p2.y) * (p1.y - p2.y)).sqrt()
+        };
+
+        let radius_func = |d1: &f64, d2: &f64| (d1 - d2).abs();
+
+        let tree = kdtree::KDTree::new(data, dimensions, dist_func, radius_func);
+
+        let neighbors = tree.find_within_radius(&Point { x: 260.0, y: 585.0 }, 150.0);
+
+        println!("{:?}", neighbors)
+    }
+
+    #[test]
+    fn test_kdtree_widest_spread_strategy() {
+        let data = vec![
+            Point { x: 615.0, y: 40.0 },
+            Point { x: 207.0, y: 313.0 },
+            Point { x: 751.0, y: 177.0 },
+            Point { x: 479.0, y: 449.0 },
+            Point { x: 70.0, y: 721.0 },
+            Point { x: 343.0, y: 858.0 },
+            Point { x: 888.0, y: 585.0 },
+        ];
+
+        let dimensions = vec![|p: &Point| p.x, |p: &Point| p.y];
+
+        let dist_func = |p1: &Point, p2: &Point| {
+            ((p1.x - p2.x) * (p1.x - p2.x) + (p1.y - p2.y) * (p1.y - p2.y)).sqrt()
+        };
+
+        let radius_func = |d1: &f64, d2: &f64| (d1 - d2).abs();
+
+        let tree = kdtree::KDTree::new_with_strategy(
+            data,
+            dimensions,
+            dist_func,
+            radius_func,
+            kdtree::SplitStrategy::WidestSpread,
+        );
+
+        let neighbors = tree.find_k_nearest_neighbors(&Point { x: 782.0, y: 780.0 }, 1);
+
+        println!("{:?}", neighbors)
+    }
+
+    #[test]
+    fn test_kdforest_insert_and_remove() {
+        let dimensions = vec![|p: &Point| p.x, |p: &Point| p.y];
+
+        let dist_func = |p1: &Point, p2: &Point| {
+            ((p1.x - p2.x) * (p1.x - p2.x) + (p1.y - p2.y) * (p1.y - p2.y)).sqrt()
+        };
+
+        let radius_func = |d1: &f64, d2: &f64| (d1 - d2).abs();
+
+        let mut forest = forest::KDForest::new(dimensions, dist_func, radius_func);
+
+        let points = [
+            Point { x: 272.0, y: 59.0 },
+            Point { x: 481.0, y: 144.0 },
+            Point { x: 915.0, y: 157.0 },
+            Point { x: 259.0, y: 189.0 },
+            Point { x: 913.0, y: 276.0 },
+        ];
+
+        for point in points.iter().cloned() {
+            forest.insert(point);
+        }
+
+        let neighbors = forest.find_k_nearest_neighbors(&Point { x: 260.0, y: 585.0 }, 3);
+        println!("{:?}", neighbors);
+
+        forest.remove(&points[0]);
+
+        let neighbors = forest.find_within_radius(&Point { x: 260.0, y: 585.0 }, 700.0);
+        println!("{:?}", neighbors)
+    }
+
+    #[test]
+    fn test_kdtree_find_k_nearest_neighbors_approx() {
+        let data = vec![
+            Point { x: 272.0, y: 59.0 },
+            Point { x: 481.0, y: 144.0 },
+            Point { x: 915.0, y: 157.0 },
+            Point { x: 259.0, y: 189.0 },
+            Point { x: 913.0, y: 276.0 },
+            Point { x: 139.0, y: 310.0 },
+            Point { x: 821.0, y: 386.0 },
+            Point { x: 622.0, y: 410.0 },
+            Point { x: 281.0, y: 467.0 },
+            Point { x: 43.0, y: 480.0 },
+        ];
+
+        let dimensions = vec![|p: &Point| p.x, |p: &Point| p.y];
+
+        let dist_func = |p1: &Point, p2: &Point| {
+            ((p1.x - p2.x) * (p1.x - p2.x) + (p1.y - p2.y) * (p1.y - p2.y)).sqrt()
+        };
+
+        let radius_func = |d1: &f64, d2: &f64| (d1 - d2).abs();
+
+        let tree = kdtree::KDTree::new(data, dimensions, dist_func, radius_func);
+
+        let neighbors = tree.find_k_nearest_neighbors_approx(&Point { x: 260.0, y: 585.0 }, 3, 0.2);
+
+        println!("{:?}", neighbors)
+    }
+
+    #[test]
+    fn test_vptree_find_k_nearest_neighbors() {
+        let data = vec![
+            Point { x: 272.0, y: 59.0 },
+            Point { x: 481.0, y: 144.0 },
+            Point { x: 915.0, y: 157.0 },
+            Point { x: 259.0, y: 189.0 },
+            Point { x: 913.0, y: 276.0 },
+            Point { x: 139.0, y: 310.0 },
+            Point { x: 821.0, y: 386.0 },
+            Point { x: 622.0, y: 410.0 },
+            Point { x: 281.0, y: 467.0 },
+            Point { x: 43.0, y: 480.0 },
+        ];
+
+        let dist_func = |p1: &Point, p2: &Point| {
+            ((p1.x - p2.x) * (p1.x - p2.x) + (p1.y - p2.y) * (p1.y - p2.y)).sqrt()
+        };
+
+        let target = Point { x: 260.0, y: 585.0 };
+        let k = 3;
+
+        let mut brute_force: Vec<f64> = data.iter().map(|p| dist_func(&target, p)).collect();
+        brute_force.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let expected = &brute_force[..k];
+
+        let tree = vptree::VPTree::new(data, dist_func);
+
+        let neighbors = tree.find_k_nearest_neighbors(&target, k);
+
+        assert_eq!(neighbors.len(), k);
+        let mut actual: Vec<f64> = neighbors.iter().map(|p| dist_func(&target, p)).collect();
+        actual.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        assert_eq!(actual, expected);
+
+        println!("{:?}", neighbors)
+    }
+
+    #[test]
+    fn test_kdtree_materialized_distance() {
+        let data = vec![
+            Point { x: 272.0, y: 59.0 },
+            Point { x: 481.0, y: 144.0 },
+            Point { x: 915.0, y: 157.0 },
+            Point { x: 259.0, y: 189.0 },
+            Point { x: 913.0, y: 276.0 },
+        ];
+
+        let dimensions = vec![|p: &Point| p.x, |p: &Point| p.y];
+
+        // Ordering and pruning only need a value monotonic with the true
+        // distance, so skip the sqrt here and only pay for it on output.
+        let dist_func = |p1: &Point, p2: &Point| {
+            (p1.x - p2.x) * (p1.x - p2.x) + (p1.y - p2.y) * (p1.y - p2.y)
+        };
+
+        let radius_func = |d1: &f64, d2: &f64| (d1 - d2) * (d1 - d2);
+
+        let tree = kdtree::KDTree::new(data, dimensions, dist_func, radius_func)
+            .with_materializer(|d| d.sqrt());
+
+        let neighbors =
+            tree.find_k_nearest_neighbors_with_dist(&Point { x: 260.0, y: 585.0 }, 2);
 
         println!("{:?}", neighbors)
     }
@@ -0,0 +1,199 @@
+use std::{
+    collections::BinaryHeap,
+    fmt::{Debug, Display},
+};
+
+use crate::kdtree::PairDistanceValue;
+
+#[derive(Debug)]
+struct VPNode<T, D> {
+    vantage: T,
+    mu: D,
+    inside: Option<Box<VPNode<T, D>>>,
+    outside: Option<Box<VPNode<T, D>>>,
+}
+
+/// A vantage-point tree: a metric-space index built only from `dist_func`,
+/// for metrics that cannot be decomposed per-axis (edit distance, cosine on
+/// sparse vectors, geodesic distances, ...). Exposes the same
+/// `find_k_nearest_neighbors`/`find_within_radius` surface as `KDTree` so
+/// callers can pick whichever tree fits their space.
+pub struct VPTree<T, D, FDist>
+where
+    T: Clone,
+    D: PartialOrd + Copy + std::ops::Add<Output = D> + std::ops::Sub<Output = D>,
+    FDist: Fn(&T, &T) -> D,
+{
+    root: Option<Box<VPNode<T, D>>>,
+    dist_func: FDist,
+}
+
+impl<T, D, FDist> VPTree<T, D, FDist>
+where
+    T: Clone,
+    D: PartialOrd + Copy + std::ops::Add<Output = D> + std::ops::Sub<Output = D>,
+    FDist: Fn(&T, &T) -> D,
+{
+    pub fn new(data: Vec<T>, dist_func: FDist) -> Self {
+        let mut data = data;
+        Self {
+            root: Self::build_node(&mut data, &dist_func),
+            dist_func,
+        }
+    }
+
+    fn partition(data: &mut [T], vantage: &T, dist_func: &FDist) -> usize {
+        let len = data.len();
+        let pivot = dist_func(vantage, &data[len - 1]);
+        let mut i = 0;
+        for j in 0..(len - 1) {
+            if dist_func(vantage, &data[j]) <= pivot {
+                data.swap(i, j);
+                i += 1;
+            }
+        }
+        data.swap(i, len - 1);
+        i
+    }
+
+    fn quick_selection<'a>(data: &'a mut [T], vantage: &T, dist_func: &FDist, ind: usize) -> &'a T {
+        if data.len() == 1 {
+            return &data[0];
+        }
+
+        let pivot_ind = Self::partition(data, vantage, dist_func);
+        if ind == pivot_ind {
+            &data[ind]
+        } else if ind < pivot_ind {
+            Self::quick_selection(&mut data[..pivot_ind], vantage, dist_func, ind)
+        } else {
+            Self::quick_selection(&mut data[pivot_ind + 1..], vantage, dist_func, ind - pivot_ind - 1)
+        }
+    }
+
+    fn build_node(data: &mut [T], dist_func: &FDist) -> Option<Box<VPNode<T, D>>> {
+        if data.is_empty() {
+            return None;
+        }
+
+        let last = data.len() - 1;
+        let vantage = data[last].clone();
+
+        if last == 0 {
+            return Some(Box::new(VPNode {
+                mu: dist_func(&vantage, &vantage),
+                vantage,
+                inside: None,
+                outside: None,
+            }));
+        }
+
+        let rest = &mut data[..last];
+        let median_ind = rest.len() / 2;
+        let mu = {
+            let median = Self::quick_selection(rest, &vantage, dist_func, median_ind);
+            dist_func(&vantage, median)
+        };
+
+        // `rest[median_ind]` is the pivot quick_selection settled on (dist ==
+        // mu); it belongs inside by the `dist <= mu` convention, so keep it
+        // in the inside slice rather than discarding it between the two
+        // recursive calls.
+        Some(Box::new(VPNode {
+            vantage,
+            mu,
+            inside: Self::build_node(&mut rest[..median_ind + 1], dist_func),
+            outside: Self::build_node(&mut rest[median_ind + 1..], dist_func),
+        }))
+    }
+
+    pub fn find_k_nearest_neighbors(&self, target: &T, k: usize) -> Vec<T> {
+        let mut heap = BinaryHeap::new();
+        self.knn(target, k, &self.root, &mut heap);
+        let mut pairs = heap.into_vec();
+        pairs.sort_by(|a, b| {
+            a.dist
+                .partial_cmp(&b.dist)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        pairs.iter().map(|p| p.value.clone()).collect()
+    }
+
+    fn knn(
+        &self,
+        target: &T,
+        k: usize,
+        node: &Option<Box<VPNode<T, D>>>,
+        heap: &mut BinaryHeap<PairDistanceValue<T, D>>,
+    ) {
+        match node {
+            None => return,
+            Some(node) => {
+                let dist = (self.dist_func)(target, &node.vantage);
+
+                if heap.len() < k {
+                    heap.push(PairDistanceValue {
+                        value: node.vantage.clone(),
+                        dist,
+                    });
+                } else if heap.peek().is_some_and(|max| dist < max.dist) {
+                    heap.pop();
+                    heap.push(PairDistanceValue {
+                        value: node.vantage.clone(),
+                        dist,
+                    });
+                }
+
+                let explore_inside = heap.len() < k
+                    || heap.peek().is_some_and(|max| dist < node.mu + max.dist);
+                if explore_inside {
+                    self.knn(target, k, &node.inside, heap);
+                }
+
+                let explore_outside = heap.len() < k
+                    || heap.peek().is_some_and(|max| dist + max.dist > node.mu);
+                if explore_outside {
+                    self.knn(target, k, &node.outside, heap);
+                }
+            }
+        }
+    }
+
+    pub fn find_within_radius(&self, target: &T, radius: D) -> Vec<T> {
+        let mut result = Vec::new();
+        self.radius_search(target, &radius, &self.root, &mut result);
+        result
+    }
+
+    fn radius_search(&self, target: &T, radius: &D, node: &Option<Box<VPNode<T, D>>>, result: &mut Vec<T>) {
+        match node {
+            None => return,
+            Some(node) => {
+                let dist = (self.dist_func)(target, &node.vantage);
+
+                if dist <= *radius {
+                    result.push(node.vantage.clone());
+                }
+
+                if dist <= node.mu + *radius {
+                    self.radius_search(target, radius, &node.inside, result);
+                }
+
+                if dist + *radius > node.mu {
+                    self.radius_search(target, radius, &node.outside, result);
+                }
+            }
+        }
+    }
+}
+
+impl<T, D, FDist> Display for VPTree<T, D, FDist>
+where
+    T: Clone + Debug,
+    D: PartialOrd + Copy + Debug + std::ops::Add<Output = D> + std::ops::Sub<Output = D>,
+    FDist: Fn(&T, &T) -> D,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#?}", self.root)
+    }
+}